@@ -3,7 +3,9 @@
 //! constantly read .osm files, and to visualize the intermediate state with map_editor.
 
 use std::collections::BTreeMap;
+use std::fmt;
 
+use anyhow::bail;
 use osm2streets::{osm, StreetNetwork};
 use serde::{Deserialize, Serialize};
 
@@ -12,30 +14,46 @@ use abstutil::{
     deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
     Tags,
 };
-use geom::{PolyLine, Polygon, Pt2D};
+use geom::{Duration, PolyLine, Polygon, Pt2D};
+// Requires the `rgb` crate's `serde` feature in Cargo.toml -- RGB8 only implements
+// Serialize/Deserialize with it enabled, and RawTransitRoute below derives both.
+use rgb::RGB8;
+#[cfg(feature = "ts_export")]
+use ts_rs::TS;
 
 pub use self::types::{Amenity, AmenityType, AreaType};
 
+#[cfg(feature = "ts_export")]
+pub use self::ts_export::export_ts_bindings;
+
 mod types;
+#[cfg(feature = "ts_export")]
+mod ts_export;
 
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
 pub struct RawMap {
     pub name: MapName,
+    #[cfg_attr(feature = "ts_export", ts(type = "any"))]
     pub streets: StreetNetwork,
     #[serde(
         serialize_with = "serialize_btreemap",
         deserialize_with = "deserialize_btreemap"
     )]
+    #[cfg_attr(feature = "ts_export", ts(type = "Array<[any, RawBuilding]>"))]
     pub buildings: BTreeMap<osm::OsmID, RawBuilding>,
     pub areas: Vec<RawArea>,
     pub parking_lots: Vec<RawParkingLot>,
+    #[cfg_attr(feature = "ts_export", ts(type = "Array<[any, Array<[number, number]>]>"))]
     pub parking_aisles: Vec<(osm::WayID, Vec<Pt2D>)>,
     pub transit_routes: Vec<RawTransitRoute>,
     #[serde(
         serialize_with = "serialize_btreemap",
         deserialize_with = "deserialize_btreemap"
     )]
-    pub transit_stops: BTreeMap<String, RawTransitStop>,
+    #[cfg_attr(feature = "ts_export", ts(type = "Array<[string, RawTransitStop]>"))]
+    pub transit_stops: BTreeMap<RawTransitStopID, RawTransitStop>,
     /// Per road, what bus routes run along it?
     ///
     /// This is scraped from OSM relations for every map, unlike the more detailed `transit_routes`
@@ -45,6 +63,7 @@ pub struct RawMap {
         serialize_with = "serialize_multimap",
         deserialize_with = "deserialize_multimap"
     )]
+    #[cfg_attr(feature = "ts_export", ts(type = "Array<[any, string[]]>"))]
     pub bus_routes_on_roads: MultiMap<osm::WayID, String>,
 }
 
@@ -113,11 +132,66 @@ impl RawMap {
     pub fn get_city_name(&self) -> &CityName {
         &self.name.city
     }
+
+    /// Looks up a transit stop by ID. Panics if it doesn't exist; call `validate_transit_stops`
+    /// after importing to check this ahead of time.
+    pub fn get_transit_stop(&self, id: &RawTransitStopID) -> &RawTransitStop {
+        &self.transit_stops[id]
+    }
+
+    /// Resolves every stop a route visits, in order.
+    pub fn transit_route_stops(&self, route: &RawTransitRoute) -> Vec<&RawTransitStop> {
+        route
+            .stops
+            .iter()
+            .map(|id| self.get_transit_stop(id))
+            .collect()
+    }
+
+    /// Checks that every stop referenced by a transit route -- either in its `stops` list or in
+    /// its `schedule`'s concrete trips -- actually exists in `transit_stops`. convert_osm should
+    /// call this after importing GTFS data.
+    pub fn validate_transit_stops(&self) -> anyhow::Result<()> {
+        for route in &self.transit_routes {
+            for id in &route.stops {
+                self.check_transit_stop_exists(route, id)?;
+            }
+            if let RawTransitSchedule::Trips(trips) = &route.schedule {
+                for trip in trips {
+                    for (id, _) in trip {
+                        self.check_transit_stop_exists(route, id)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_transit_stop_exists(
+        &self,
+        route: &RawTransitRoute,
+        id: &RawTransitStopID,
+    ) -> anyhow::Result<()> {
+        if !self.transit_stops.contains_key(id) {
+            bail!(
+                "{}'s route {} ({}) references stop {:?}, but it's not in transit_stops",
+                self.name,
+                route.gtfs_id,
+                route.long_name,
+                id
+            );
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
 pub struct RawBuilding {
+    #[cfg_attr(feature = "ts_export", ts(type = "Array<[number, number]>"))]
     pub polygon: Polygon,
+    #[cfg_attr(feature = "ts_export", ts(type = "Record<string, string>"))]
     pub osm_tags: Tags,
     pub public_garage_name: Option<String>,
     pub num_parking_spots: usize,
@@ -125,43 +199,225 @@ pub struct RawBuilding {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
 pub struct RawArea {
     pub area_type: AreaType,
+    #[cfg_attr(feature = "ts_export", ts(type = "Array<[number, number]>"))]
     pub polygon: Polygon,
+    #[cfg_attr(feature = "ts_export", ts(type = "Record<string, string>"))]
     pub osm_tags: Tags,
+    #[cfg_attr(feature = "ts_export", ts(type = "any"))]
     pub osm_id: osm::OsmID,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
 pub struct RawParkingLot {
+    #[cfg_attr(feature = "ts_export", ts(type = "any"))]
     pub osm_id: osm::OsmID,
+    #[cfg_attr(feature = "ts_export", ts(type = "Array<[number, number]>"))]
     pub polygon: Polygon,
+    #[cfg_attr(feature = "ts_export", ts(type = "Record<string, string>"))]
     pub osm_tags: Tags,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
 pub struct RawTransitRoute {
     pub long_name: String,
     pub short_name: String,
     pub gtfs_id: String,
     /// This may begin and/or end inside or outside the map boundary.
+    #[cfg_attr(feature = "ts_export", ts(type = "Array<[number, number]>"))]
     pub shape: PolyLine,
     /// Entries into transit_stops
-    pub stops: Vec<String>,
+    pub stops: Vec<RawTransitStopID>,
     pub route_type: RawTransitType,
-    // TODO Schedule
+    #[cfg_attr(feature = "ts_export", ts(type = "any"))]
+    pub schedule: RawTransitSchedule,
+    /// From GTFS `route_color`, or `RawTransitType::default_color` if the feed omits it.
+    #[cfg_attr(feature = "ts_export", ts(type = "{ r: number, g: number, b: number }"))]
+    pub color: RGB8,
+    /// From GTFS `route_text_color`, or `RawTransitType::default_text_color` if the feed omits
+    /// it.
+    #[cfg_attr(feature = "ts_export", ts(type = "{ r: number, g: number, b: number }"))]
+    pub text_color: RGB8,
+}
+
+/// When vehicles along a RawTransitRoute actually run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RawTransitSchedule {
+    /// Concrete trips straight from GTFS `stop_times`, one entry per service pattern -- a route
+    /// can have multiple trips visiting different subsets of stops, and those patterns are kept
+    /// distinct rather than merged together. Each trip is the arrival offset from midnight at
+    /// every stop it visits, in order. Offsets can exceed 24 hours for trips starting after
+    /// midnight; stops outside the map boundary are omitted from the trip, but the rest of the
+    /// timing is kept as-is.
+    Trips(Vec<Vec<(RawTransitStopID, Duration)>>),
+    /// A frequency-based service from GTFS `frequencies.txt`: vehicles depart every `headway`
+    /// between `start_time` and `end_time`. The importer is responsible for expanding this into
+    /// concrete trips when simulating.
+    Frequencies(Vec<RawTransitFrequency>),
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RawTransitFrequency {
+    /// Offset from midnight; may exceed 24 hours.
+    pub start_time: Duration,
+    /// Offset from midnight; may exceed 24 hours.
+    pub end_time: Duration,
+    pub headway: Duration,
+    /// GTFS `exact_times`: if true, vehicles are scheduled to depart exactly every `headway`,
+    /// rather than just approximately.
+    pub exact_times: bool,
+}
+
+/// The mode of a transit route, per GTFS `route_type`. This is richer than a simple bus/train
+/// split, so the simulation and renderer can tell a street-running tram from a grade-separated
+/// subway, and so modes that don't belong on the street network (like ferries) aren't forced onto
+/// it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
 pub enum RawTransitType {
+    /// GTFS route_type 0: street-level tram, streetcar, or light rail.
+    Tram,
+    /// GTFS route_type 1: underground or elevated metro.
+    Subway,
+    /// GTFS route_type 2: intercity or long-distance rail.
+    Rail,
+    /// GTFS route_type 3: street-running bus.
     Bus,
-    Train,
+    /// GTFS route_type 4: boat or ferry.
+    Ferry,
+    /// GTFS route_type 5: street-level cable car, pulled by an underground cable.
+    CableTram,
+    /// GTFS route_type 6: gondola or suspended cable car.
+    AerialLift,
+    /// GTFS route_type 7: rail car pulled up a steep incline by a cable.
+    Funicular,
+}
+
+impl From<gtfs_structures::RouteType> for RawTransitType {
+    fn from(route_type: gtfs_structures::RouteType) -> RawTransitType {
+        match route_type {
+            gtfs_structures::RouteType::Tramway => RawTransitType::Tram,
+            gtfs_structures::RouteType::Subway => RawTransitType::Subway,
+            gtfs_structures::RouteType::Rail => RawTransitType::Rail,
+            gtfs_structures::RouteType::Bus => RawTransitType::Bus,
+            gtfs_structures::RouteType::Ferry => RawTransitType::Ferry,
+            gtfs_structures::RouteType::CableCar => RawTransitType::CableTram,
+            gtfs_structures::RouteType::Gondola => RawTransitType::AerialLift,
+            gtfs_structures::RouteType::Funicular => RawTransitType::Funicular,
+            // Coach, Air, Taxi, and any extended (Hundreds-range) code we don't otherwise
+            // recognize. Defaulting to Bus keeps these on the street network, which is the safest
+            // fallback for modes we can't place.
+            _ => RawTransitType::Bus,
+        }
+    }
+}
+
+impl RawTransitType {
+    /// GTFS defines a route with no `route_color` as having a white background, regardless of
+    /// mode. Kept as a method on `RawTransitType` (rather than a free function) so a future mode
+    /// that wants a different fallback -- map_editor's renderer already distinguishes them -- has
+    /// somewhere to plug that in without changing every call site.
+    pub fn default_color(self) -> RGB8 {
+        RGB8::new(255, 255, 255)
+    }
+
+    /// GTFS defines a route with no `route_text_color` as having black text, regardless of mode.
+    /// See `default_color` for why this dispatches on `self`.
+    pub fn default_text_color(self) -> RGB8 {
+        RGB8::new(0, 0, 0)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
 pub struct RawTransitStop {
     pub gtfs_id: String,
     /// Only stops within a map's boundary are kept
+    #[cfg_attr(feature = "ts_export", ts(type = "[number, number]"))]
     pub position: Pt2D,
     pub name: String,
 }
+
+/// A GTFS stop id, distinguished at the type level from other strings so the compiler catches a
+/// route referencing the wrong kind of ID.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export, type = "string"))]
+pub struct RawTransitStopID(pub String);
+
+impl fmt::Display for RawTransitStopID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for RawTransitStopID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::Pt2D;
+
+    fn route_visiting(stops: Vec<RawTransitStopID>) -> RawTransitRoute {
+        RawTransitRoute {
+            long_name: "Test Route".to_string(),
+            short_name: "1".to_string(),
+            gtfs_id: "route1".to_string(),
+            shape: PolyLine::must_new(vec![Pt2D::new(0.0, 0.0), Pt2D::new(1.0, 1.0)]),
+            stops,
+            route_type: RawTransitType::Bus,
+            schedule: RawTransitSchedule::Trips(Vec::new()),
+            color: RawTransitType::Bus.default_color(),
+            text_color: RawTransitType::Bus.default_text_color(),
+        }
+    }
+
+    #[test]
+    fn validate_transit_stops_rejects_dangling_reference() {
+        let mut map = RawMap::blank(MapName::new("zz", "test", "test"));
+        map.transit_routes
+            .push(route_visiting(vec![RawTransitStopID("missing".to_string())]));
+        assert!(map.validate_transit_stops().is_err());
+    }
+
+    #[test]
+    fn validate_transit_stops_accepts_known_stop() {
+        let mut map = RawMap::blank(MapName::new("zz", "test", "test"));
+        let id = RawTransitStopID("stop1".to_string());
+        map.transit_stops.insert(
+            id.clone(),
+            RawTransitStop {
+                gtfs_id: "stop1".to_string(),
+                position: Pt2D::new(0.0, 0.0),
+                name: "Test Stop".to_string(),
+            },
+        );
+        map.transit_routes.push(route_visiting(vec![id]));
+        assert!(map.validate_transit_stops().is_ok());
+    }
+
+    #[test]
+    fn validate_transit_stops_checks_schedule_trips_too() {
+        let mut map = RawMap::blank(MapName::new("zz", "test", "test"));
+        let mut route = route_visiting(Vec::new());
+        route.schedule = RawTransitSchedule::Trips(vec![vec![(
+            RawTransitStopID("missing".to_string()),
+            Duration::ZERO,
+        )]]);
+        map.transit_routes.push(route);
+        assert!(map.validate_transit_stops().is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! Exports TypeScript type definitions for `RawMap` and its sub-structs, so web tools (and
+//! map_editor's own visualizations) that consume the serialized `RawMap` can type against it
+//! instead of hand-maintaining interfaces that drift from these Rust structs.
+//!
+//! This whole module only exists when the `ts_export` feature is on, so `ts-rs` doesn't weigh
+//! down the normal build. Call `export_ts_bindings` from a small bin target to regenerate the
+//! `.d.ts` bundle; there's nothing test-specific about it.
+
+use std::path::Path;
+
+use ts_rs::{ExportError, TS};
+
+use crate::{
+    Amenity, AmenityType, AreaType, RawArea, RawBuilding, RawMap, RawParkingLot, RawTransitRoute,
+    RawTransitStop, RawTransitStopID, RawTransitType,
+};
+
+/// Writes a `.d.ts` file per exported type into `dir`.
+pub fn export_ts_bindings(dir: &Path) -> Result<(), ExportError> {
+    RawMap::export_to(dir.join("RawMap.d.ts"))?;
+    RawBuilding::export_to(dir.join("RawBuilding.d.ts"))?;
+    RawArea::export_to(dir.join("RawArea.d.ts"))?;
+    RawParkingLot::export_to(dir.join("RawParkingLot.d.ts"))?;
+    RawTransitRoute::export_to(dir.join("RawTransitRoute.d.ts"))?;
+    RawTransitType::export_to(dir.join("RawTransitType.d.ts"))?;
+    RawTransitStop::export_to(dir.join("RawTransitStop.d.ts"))?;
+    RawTransitStopID::export_to(dir.join("RawTransitStopID.d.ts"))?;
+    Amenity::export_to(dir.join("Amenity.d.ts"))?;
+    AmenityType::export_to(dir.join("AmenityType.d.ts"))?;
+    AreaType::export_to(dir.join("AreaType.d.ts"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_ts_bindings_writes_every_type() {
+        let dir = std::env::temp_dir().join("raw_map_ts_export_test");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        export_ts_bindings(&dir).unwrap();
+
+        for name in [
+            "RawMap",
+            "RawBuilding",
+            "RawArea",
+            "RawParkingLot",
+            "RawTransitRoute",
+            "RawTransitType",
+            "RawTransitStop",
+            "RawTransitStopID",
+            "Amenity",
+            "AmenityType",
+            "AreaType",
+        ] {
+            assert!(
+                dir.join(format!("{name}.d.ts")).exists(),
+                "export_ts_bindings didn't write {name}.d.ts"
+            );
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
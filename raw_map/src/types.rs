@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts_export")]
+use ts_rs::TS;
+
+use abstutil::Tags;
+
+/// A business located inside a building, used to generate pop-up info and to guess what trips are
+/// likely to originate or end there.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
+pub struct Amenity {
+    pub name: String,
+    /// The coarse category of this amenity; the specific amenity/shop value from OSM is only kept
+    /// in `osm_tags`.
+    pub amenity_type: AmenityType,
+    #[cfg_attr(feature = "ts_export", ts(type = "Record<string, string>"))]
+    pub osm_tags: Tags,
+}
+
+/// A coarse categorization of `Amenity`, used to choose a rendering icon and to let the
+/// simulation and renderer treat similar businesses alike.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
+pub enum AmenityType {
+    Bank,
+    Bar,
+    Cafe,
+    ConvenienceStore,
+    Food,
+    GroceryStore,
+    Hospital,
+    Hotel,
+    Pharmacy,
+    Religious,
+    School,
+    Shopping,
+    University,
+}
+
+/// The kind of a `RawArea`, used to pick a rendering style.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[cfg_attr(feature = "ts_export", ts(export))]
+pub enum AreaType {
+    Park,
+    Water,
+    PedestrianIsland,
+    Island,
+}